@@ -5,27 +5,114 @@ use rayon::prelude::*;
 use std::{
     collections::HashMap,
     sync::{atomic::AtomicU32, Mutex},
+    time::{Duration, Instant},
     vec,
 };
 
-const MAX_DEPTH: i32 = 3;
+/// How long a search is allowed to keep deepening before it must return the
+/// best result found at the last fully completed depth. Shared by every
+/// engine in this module (and `analysis`, which mirrors this search).
+pub(crate) const TIME_THRESHOLD: Duration = Duration::from_millis(1500);
+
+/// Tracks how much of a search's time budget has elapsed.
+///
+/// Cloned cheaply (just two fields) and shared by every parallel search call
+/// within a single invocation so they all agree on when to bail out.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TimeKeeper {
+    start: Instant,
+    threshold: Duration,
+}
+
+impl TimeKeeper {
+    pub(crate) fn new(threshold: Duration) -> Self {
+        TimeKeeper {
+            start: Instant::now(),
+            threshold,
+        }
+    }
+
+    pub(crate) fn is_time_over(&self) -> bool {
+        self.start.elapsed() >= self.threshold
+    }
+}
+
+/// Which side of the true value a stored transposition table entry
+/// represents, mirroring the usual alpha-beta bound bookkeeping.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    LowerBound,
+    UpperBound,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TranspositionEntry {
+    depth: i32,
+    value: i32,
+    flag: Bound,
+}
+
+lazy_static! {
+    static ref TRANSPOSITION_TABLE: Mutex<HashMap<u64, TranspositionEntry>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Returns `None` when the search was abandoned partway through because the
+/// time budget ran out; callers must discard such a result rather than treat
+/// it as a real evaluation.
+fn minimax(
+    state: &GameState,
+    depth: i32,
+    alpha: i32,
+    beta: i32,
+    maximizing_player: bool,
+    time_keeper: &TimeKeeper,
+) -> Option<i32> {
+    if time_keeper.is_time_over() {
+        return None;
+    }
 
-fn minimax(state: &GameState, depth: i32, alpha: i32, beta: i32, maximizing_player: bool) -> i32 {
     if depth == 0 {
-        return evaluate(&state);
+        return Some(evaluate(&state));
     }
 
     let mut alpha = alpha;
     let mut beta = beta;
-    if maximizing_player {
+
+    let hash = state.hash();
+    if let Some(entry) = TRANSPOSITION_TABLE
+        .lock()
+        .expect("should obtain lock")
+        .get(&hash)
+    {
+        if entry.depth >= depth {
+            match entry.flag {
+                Bound::Exact => return Some(entry.value),
+                Bound::LowerBound => alpha = alpha.max(entry.value),
+                Bound::UpperBound => beta = beta.min(entry.value),
+            }
+            if alpha >= beta {
+                return Some(entry.value);
+            }
+        }
+    }
+
+    // Captured after the TT probe so the flag classification below reflects
+    // the window actually searched, not the caller's original window.
+    let original_alpha = alpha;
+    let original_beta = beta;
+
+    let value = if maximizing_player {
         let mut max_eval = i32::MIN;
         for action in generate_moves(&state) {
             let mut new_state = state.clone();
             let result = new_state.make_move(action).expect("move to possible moves");
             let eval = match result {
-                GameEnd::NotEnd => minimax(&new_state, depth - 1, alpha, beta, false),
+                GameEnd::NotEnd => minimax(&new_state, depth - 1, alpha, beta, false, time_keeper)?,
                 GameEnd::Win => i32::MAX,
-                GameEnd::Lost => i32::MIN,
+                GameEnd::Lost | GameEnd::Forbidden => i32::MIN,
             };
             max_eval = max_eval.max(eval);
             alpha = alpha.max(eval);
@@ -40,9 +127,9 @@ fn minimax(state: &GameState, depth: i32, alpha: i32, beta: i32, maximizing_play
             let mut new_state = state.clone();
             let result = new_state.make_move(action).expect("move to possible moves");
             let eval = match result {
-                GameEnd::NotEnd => minimax(&new_state, depth - 1, alpha, beta, true),
+                GameEnd::NotEnd => minimax(&new_state, depth - 1, alpha, beta, true, time_keeper)?,
                 GameEnd::Win => i32::MIN,
-                GameEnd::Lost => i32::MAX,
+                GameEnd::Lost | GameEnd::Forbidden => i32::MAX,
             };
             min_eval = min_eval.min(eval);
             beta = beta.min(eval);
@@ -51,51 +138,90 @@ fn minimax(state: &GameState, depth: i32, alpha: i32, beta: i32, maximizing_play
             }
         }
         min_eval
-    }
+    };
+
+    let flag = if value <= original_alpha {
+        Bound::UpperBound
+    } else if value >= original_beta {
+        Bound::LowerBound
+    } else {
+        Bound::Exact
+    };
+
+    TRANSPOSITION_TABLE.lock().expect("should obtain lock").insert(
+        hash,
+        TranspositionEntry { depth, value, flag },
+    );
+
+    Some(value)
 }
 
 pub fn best_move(state: &GameState) -> (Option<Point>, i32) {
-    COUNTER.store(0, std::sync::atomic::Ordering::Relaxed);
+    reset_node_counter();
     EVALUATE_CACHE.lock().expect("should obtain lock").clear();
+    TRANSPOSITION_TABLE.lock().expect("should obtain lock").clear();
 
+    let time_keeper = TimeKeeper::new(TIME_THRESHOLD);
+
+    let mut best_move = None;
     let mut best_eval = i32::MIN;
-    let mut best_moves = Vec::new();
-
-    let actions: Vec<((usize, usize), i32)> = generate_moves(state)
-        .into_par_iter()
-        .map(|action| {
-            let mut state_snapshot = state.clone();
-            state_snapshot
-                .make_move(action)
-                .expect("move to possible moves");
-            let eval = minimax(&state_snapshot, MAX_DEPTH, i32::MIN, i32::MAX, false);
-
-            (action, eval)
-        })
-        .collect();
-
-    for (action, eval) in actions {
-        if eval > best_eval {
-            best_eval = eval;
-            best_moves = vec![action];
-        } else if eval == best_eval {
-            best_moves.push(action);
+    let mut depth = 1;
+
+    while !time_keeper.is_time_over() {
+        let actions: Vec<((usize, usize), Option<i32>)> = generate_moves(state)
+            .into_par_iter()
+            .map(|action| {
+                let mut state_snapshot = state.clone();
+                let result = state_snapshot
+                    .make_move(action)
+                    .expect("move to possible moves");
+                let eval = match result {
+                    GameEnd::NotEnd => {
+                        minimax(&state_snapshot, depth, i32::MIN, i32::MAX, false, &time_keeper)
+                    }
+                    GameEnd::Win => Some(i32::MAX),
+                    GameEnd::Lost | GameEnd::Forbidden => Some(i32::MIN),
+                };
+
+                (action, eval)
+            })
+            .collect();
+
+        // A half-finished depth is worthless: if any branch bailed out on
+        // time, the comparisons above are no longer apples-to-apples, so we
+        // keep the previous (fully searched) depth's result instead.
+        if actions.iter().any(|(_, eval)| eval.is_none()) {
+            break;
         }
-    }
 
-    let best_move = match best_moves.len() {
-        0 => None,
-        1 => Some(best_moves[0]),
-        len => Some(best_moves[rand::thread_rng().gen_range(0..len)]),
-    };
+        let mut depth_eval = i32::MIN;
+        let mut depth_moves = Vec::new();
+        for (action, eval) in actions {
+            let eval = eval.expect("checked for None above");
+            if eval > depth_eval {
+                depth_eval = eval;
+                depth_moves = vec![action];
+            } else if eval == depth_eval {
+                depth_moves.push(action);
+            }
+        }
 
-    let counter = COUNTER.load(std::sync::atomic::Ordering::Relaxed);
-    println!("evaluate count: {counter}");
+        best_eval = depth_eval;
+        best_move = match depth_moves.len() {
+            0 => None,
+            1 => Some(depth_moves[0]),
+            len => Some(depth_moves[rand::thread_rng().gen_range(0..len)]),
+        };
+
+        depth += 1;
+    }
+
+    println!("evaluate count: {}", node_counter());
 
     (best_move, best_eval)
 }
 
-fn generate_moves(state: &GameState) -> Vec<Point> {
+pub(crate) fn generate_moves(state: &GameState) -> Vec<Point> {
     let mut positions = Vec::new();
 
     for x in 0..15 {
@@ -136,6 +262,17 @@ lazy_static! {
 
 static COUNTER: AtomicU32 = AtomicU32::new(0);
 
+/// Resets the shared `evaluate` call counter; callers should do this before
+/// starting a fresh search so `node_counter` reports that search's cost.
+pub(crate) fn reset_node_counter() {
+    COUNTER.store(0, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Number of `evaluate` calls made since the last `reset_node_counter`.
+pub(crate) fn node_counter() -> u32 {
+    COUNTER.load(std::sync::atomic::Ordering::Relaxed)
+}
+
 pub fn evaluate(state: &GameState) -> i32 {
     COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
     if let Some(score) = EVALUATE_CACHE
@@ -190,18 +327,6 @@ pub fn evaluate(state: &GameState) -> i32 {
     score
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
-enum PieceState {
-    Long,
-    Five,
-    RushFour,
-    LiveFour,
-    LiveThree,
-    SleepThree,
-    LiveTwo,
-    SleepTwo,
-}
-
 fn sequence(game_state: &GameState, my_piece: Piece) -> Vec<(PieceState, i32)> {
     vec![(1, 0), (0, 1), (1, 1), (1, -1)]
         .into_iter()
@@ -251,27 +376,7 @@ fn sequence(game_state: &GameState, my_piece: Piece) -> Vec<(PieceState, i32)> {
             false
         })
         .flat_map(|sequence| {
-            let patterns: Vec<(Vec<i32>, PieceState)> = with_inv(vec![
-                (vec![1, 1, 1, 1, 1, 1], PieceState::Long),
-                (vec![1, 1, 1, 1, 1], PieceState::Five),
-                (vec![0, 1, 1, 1, 1, 0], PieceState::LiveFour),
-                (vec![0, 1, 1, 1, 0, 0], PieceState::LiveThree),
-                (vec![0, 1, 0, 1, 1, 0], PieceState::LiveThree),
-                (vec![0, 0, 1, 1, 0, 0], PieceState::LiveTwo),
-                (vec![2, 1, 1, 1, 1, 0], PieceState::RushFour),
-                (vec![1, 1, 0, 1, 1], PieceState::RushFour),
-                (vec![1, 0, 1, 1, 1], PieceState::RushFour),
-                (vec![1, 0, 0, 1, 1], PieceState::SleepThree),
-                (vec![2, 1, 1, 1, 0, 0], PieceState::SleepThree),
-                (vec![2, 1, 1, 0, 1, 0], PieceState::SleepThree),
-                (vec![2, 1, 0, 1, 1, 0], PieceState::SleepThree),
-                (vec![2, 1, 1, 0, 0, 0], PieceState::SleepTwo),
-                (vec![2, 1, 0, 1, 0, 0], PieceState::SleepTwo),
-                (vec![2, 1, 0, 0, 1, 0], PieceState::SleepTwo),
-                (vec![2, 1, 0, 0, 0, 1], PieceState::SleepTwo),
-                (vec![2, 0, 1, 1, 0, 0, 2], PieceState::SleepTwo),
-                (vec![2, 0, 1, 0, 1, 0, 2], PieceState::SleepTwo),
-            ]);
+            let patterns = line_patterns();
 
             count_subarrays(&sequence, &patterns)
                 .iter()
@@ -295,84 +400,6 @@ fn next_point_valid(point: &mut Point, dir: &(i32, i32)) -> bool {
     true
 }
 
-fn count_subarrays(
-    array: &[i32],
-    subarrays: &[(Vec<i32>, PieceState)],
-) -> HashMap<PieceState, i32> {
-    let array_len = array.len();
-    let mut count = HashMap::new();
-    let mut len = usize::MAX;
-
-    for (subarray, _) in subarrays {
-        if subarray.len() < len {
-            len = subarray.len();
-        }
-    }
-
-    if array_len < len {
-        return count;
-    }
-
-    let mut i = 0;
-    while i < array_len - len + 1 {
-        let mut found = false;
-        for (subarray, state) in subarrays {
-            let subarray_len = subarray.len();
-            if i + subarray_len <= array_len && &array[i..i + subarray_len] == subarray.as_slice() {
-                let c = count.get(state).unwrap_or(&0) + 1;
-                count.insert(*state, c);
-                i += subarray_len;
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            i += 1;
-        }
-    }
-
-    count
-}
-
-fn loopback(sequence: &Vec<i32>) -> bool {
-    let mut left = 0;
-    let mut right = sequence.len() - 1;
-    while left < right {
-        if sequence[left] != sequence[right] {
-            return false;
-        }
-        left += 1;
-        right -= 1;
-    }
-    return true;
-}
-
-fn with_inv(patterns: Vec<(Vec<i32>, PieceState)>) -> Vec<(Vec<i32>, PieceState)> {
-    let mut result = Vec::new();
-
-    for (pattern, state) in patterns {
-        result.push((pattern.clone(), state));
-        if loopback(&pattern) {
-            continue;
-        }
-        result.push((inv(&pattern), state))
-    }
-
-    result
-}
-
-fn inv(sequence: &Vec<i32>) -> Vec<i32> {
-    let mut list = Vec::with_capacity(sequence.len());
-
-    let mut i = sequence.len();
-    while i > 0 {
-        i -= 1;
-        list.push(sequence[i]);
-    }
-
-    list
-}
-
 fn evaluate_location(game_state: &GameState, point: Point) -> i32 {
     let evaluate_impl = |piece, dir: (isize, isize)| {
         let mut count = 1;
@@ -419,3 +446,308 @@ fn evaluate_location(game_state: &GameState, point: Point) -> i32 {
 
     -score
 }
+
+/// Exploration constant for UCB1 (the conventional `sqrt(2)` from UCT).
+const UCT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+struct MctsNode {
+    state: GameState,
+    /// The move that was played to reach this node from its parent; `None`
+    /// for the search root.
+    mov: Option<Point>,
+    visits: u32,
+    wins: f64,
+    /// Set when `state` was reached by a move that already ended the game,
+    /// so this node never needs a random playout.
+    terminal: Option<GameEnd>,
+    unexpanded_moves: Vec<Point>,
+    children: Vec<MctsNode>,
+}
+
+impl MctsNode {
+    fn new(state: GameState, mov: Option<Point>, terminal: Option<GameEnd>) -> Self {
+        let unexpanded_moves = if terminal.is_some() {
+            Vec::new()
+        } else {
+            generate_moves(&state)
+        };
+
+        MctsNode {
+            state,
+            mov,
+            visits: 0,
+            wins: 0.0,
+            terminal,
+            unexpanded_moves,
+            children: Vec::new(),
+        }
+    }
+
+    fn ucb1(&self, parent_visits: u32) -> f64 {
+        if self.visits == 0 {
+            return f64::INFINITY;
+        }
+
+        // `self.wins` is accumulated from *this* node's own to-move
+        // perspective (see `iterate`), but the parent is choosing the child
+        // best for the parent's to-move player, i.e. the opponent of
+        // `self`'s to-move player. Negate the exploitation term so the
+        // parent favors children that are bad for whoever moves there.
+        (1.0 - self.wins / self.visits as f64)
+            + UCT_EXPLORATION * ((parent_visits as f64).ln() / self.visits as f64).sqrt()
+    }
+
+    fn select_child_index(&self) -> usize {
+        let parent_visits = self.visits;
+        (0..self.children.len())
+            .max_by(|&a, &b| {
+                self.children[a]
+                    .ucb1(parent_visits)
+                    .partial_cmp(&self.children[b].ucb1(parent_visits))
+                    .expect("ucb1 should not be NaN")
+            })
+            .expect("selecting a child requires at least one")
+    }
+
+    /// Adds one child for a random unexpanded move and returns its index.
+    fn expand(&mut self) -> usize {
+        let index = rand::thread_rng().gen_range(0..self.unexpanded_moves.len());
+        let mov = self.unexpanded_moves.swap_remove(index);
+
+        let mut child_state = self.state.clone();
+        let terminal = match child_state.make_move(mov).expect("move to possible moves") {
+            GameEnd::NotEnd => None,
+            end => Some(end),
+        };
+
+        self.children.push(MctsNode::new(child_state, Some(mov), terminal));
+        self.children.len() - 1
+    }
+
+    /// Runs one UCT iteration (select/expand/simulate/backpropagate) rooted
+    /// at this node and returns the outcome from the perspective of the
+    /// player to move at this node, so the caller can flip it before folding
+    /// it into its own `wins`.
+    fn iterate(&mut self) -> f64 {
+        let result = if let Some(end) = self.terminal {
+            terminal_value(end)
+        } else if !self.unexpanded_moves.is_empty() {
+            let index = self.expand();
+            let child = &mut self.children[index];
+            let child_result = match child.terminal {
+                Some(end) => terminal_value(end),
+                None => simulate(&child.state),
+            };
+            child.visits += 1;
+            child.wins += child_result;
+            1.0 - child_result
+        } else if self.children.is_empty() {
+            // No legal moves left anywhere under this node: the board filled up.
+            0.5
+        } else {
+            let index = self.select_child_index();
+            1.0 - self.children[index].iterate()
+        };
+
+        self.visits += 1;
+        self.wins += result;
+        result
+    }
+}
+
+/// The result of an already-decided `GameEnd`, from the perspective of the
+/// player who is about to move in the node it ended up in (i.e. the player
+/// who did *not* make the winning move).
+fn terminal_value(end: GameEnd) -> f64 {
+    match end {
+        GameEnd::Win => 0.0,
+        GameEnd::Lost | GameEnd::Forbidden => 1.0,
+        GameEnd::NotEnd => unreachable!(),
+    }
+}
+
+/// Plays uniformly random legal moves from `state` until the game ends or
+/// the board fills, returning the result from the perspective of the player
+/// to move in `state`: `1.0` win, `0.0` loss, `0.5` draw.
+fn simulate(state: &GameState) -> f64 {
+    let mut state = state.clone();
+    let player = state.current_turn();
+
+    loop {
+        let moves = generate_moves(&state);
+        if moves.is_empty() {
+            return 0.5;
+        }
+
+        let mov = moves[rand::thread_rng().gen_range(0..moves.len())];
+        let mover = state.current_turn();
+        let mover_wins = match state.make_move(mov).expect("move to possible moves") {
+            GameEnd::Win => true,
+            GameEnd::Lost | GameEnd::Forbidden => false,
+            GameEnd::NotEnd => continue,
+        };
+        return if mover_wins == (mover == player) { 1.0 } else { 0.0 };
+    }
+}
+
+/// A Monte Carlo Tree Search player, offered as an alternative to
+/// `best_move`'s minimax search: it scales with however much time it is
+/// given instead of relying on the hand-tuned pattern scores in `evaluate`.
+pub fn mcts_best_move(state: &GameState) -> (Option<Point>, f64) {
+    let time_keeper = TimeKeeper::new(TIME_THRESHOLD);
+    let mut root = MctsNode::new(state.clone(), None, None);
+
+    while !time_keeper.is_time_over() {
+        root.iterate();
+    }
+
+    root.children
+        .iter()
+        .max_by_key(|child| child.visits)
+        .map(|child| {
+            // Flip out of the child's own to-move perspective into the
+            // root's, same as `ucb1` does when selecting among children.
+            let win_rate = if child.visits == 0 {
+                0.0
+            } else {
+                1.0 - child.wins / child.visits as f64
+            };
+            (child.mov, win_rate)
+        })
+        .unwrap_or((None, 0.0))
+}
+
+#[cfg(test)]
+lazy_static! {
+    // `evaluate`/`minimax` share process-global state (`EVALUATE_CACHE`,
+    // `TRANSPOSITION_TABLE`, the node counter) by design, since a single
+    // search's parallel root-move tasks are meant to see each other's
+    // entries; `analysis` reuses `evaluate` and its node counter too. That
+    // means two *different* searches running concurrently as separate tests
+    // (in this module or `analysis`'s) would stomp on each other's counts,
+    // so any test that drives a real search takes this lock to run
+    // exclusively.
+    pub(crate) static ref SEARCH_TEST_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_move_never_picks_a_forbidden_root_move() {
+        let _guard = SEARCH_TEST_LOCK.lock().expect("should obtain lock");
+
+        // Black already has four in a row with a gap at (4, 0); playing
+        // there would merge everything into a forbidden overline under
+        // Renju, even though (4, 0) is otherwise the strongest-looking move
+        // on the board (it would win outright under Freestyle).
+        let mut state = GameState::with_rule_set(RuleSet::Renju);
+        let black_moves = [(0, 0), (1, 0), (2, 0), (3, 0), (5, 0)];
+        let white_moves = [(10, 10), (10, 11), (10, 12), (10, 13), (10, 14)];
+        for (black, white) in black_moves.into_iter().zip(white_moves) {
+            state.make_move(black).unwrap();
+            state.make_move(white).unwrap();
+        }
+
+        let mut probe = state.clone();
+        assert_eq!(probe.make_move((4, 0)).unwrap(), GameEnd::Forbidden);
+
+        let (mov, _) = best_move(&state);
+        assert_ne!(mov, Some((4, 0)));
+    }
+
+    #[test]
+    fn transposition_table_exact_hit_short_circuits_research() {
+        let _guard = SEARCH_TEST_LOCK.lock().expect("should obtain lock");
+        TRANSPOSITION_TABLE.lock().expect("should obtain lock").clear();
+        EVALUATE_CACHE.lock().expect("should obtain lock").clear();
+
+        let mut state = GameState::new();
+        state.make_move((7, 7)).unwrap();
+        state.make_move((7, 8)).unwrap();
+
+        let time_keeper = TimeKeeper::new(TIME_THRESHOLD);
+
+        reset_node_counter();
+        let first = minimax(&state, 2, i32::MIN, i32::MAX, true, &time_keeper);
+        let nodes_after_first_search = node_counter();
+        assert!(first.is_some());
+
+        let entry = *TRANSPOSITION_TABLE
+            .lock()
+            .expect("should obtain lock")
+            .get(&state.hash())
+            .expect("a full-window search should store an entry");
+        assert_eq!(entry.flag, Bound::Exact);
+
+        reset_node_counter();
+        let second = minimax(&state, 2, i32::MIN, i32::MAX, true, &time_keeper);
+        assert_eq!(second, first);
+        assert_eq!(
+            node_counter(),
+            0,
+            "an Exact transposition table hit should return without evaluating any new nodes"
+        );
+        assert!(nodes_after_first_search > 0);
+    }
+
+    #[test]
+    fn best_move_finds_the_immediate_winning_move() {
+        let _guard = SEARCH_TEST_LOCK.lock().expect("should obtain lock");
+        // Black has four in a row on row 3, blocked on one end, so (7, 3) is
+        // the single move that wins outright. An open (both-ends) four would
+        // leave more than one move tied for best at deeper search depths,
+        // making the choice among them come down to best_move's random
+        // tie-break instead of this test.
+        let mut state = GameState::new();
+        state.make_move((3, 3)).unwrap();
+        state.make_move((2, 3)).unwrap(); // White blocks the left end.
+        for (black, white) in [((4, 3), (0, 0)), ((5, 3), (0, 1)), ((6, 3), (0, 2))] {
+            state.make_move(black).unwrap();
+            state.make_move(white).unwrap();
+        }
+
+        let (mov, eval) = best_move(&state);
+        assert_eq!(mov, Some((7, 3)));
+        assert_eq!(eval, i32::MAX);
+    }
+
+    #[test]
+    fn minimax_reports_time_over_instead_of_a_partial_result() {
+        // `best_move` relies on `minimax` returning `None` once the time
+        // budget is spent so it can discard a half-finished depth instead of
+        // treating it as comparable to the last fully searched one.
+        let time_keeper = TimeKeeper::new(Duration::from_millis(0));
+        std::thread::sleep(Duration::from_millis(1));
+
+        let state = GameState::new();
+        assert_eq!(
+            minimax(&state, 3, i32::MIN, i32::MAX, true, &time_keeper),
+            None
+        );
+    }
+
+    #[test]
+    fn mcts_best_move_finds_the_immediate_winning_move() {
+        // Black has an open four on row 3, so either end, (2, 3) or (7, 3),
+        // wins outright. Regression test for the perspective-inversion bug fixed alongside
+        // this one: `ucb1`/`mcts_best_move` used to report a child's own
+        // to-move win rate instead of the root's, so a forced win could lose
+        // out to a worse-looking move.
+        let mut state = GameState::new();
+        for (black, white) in [
+            ((3, 3), (0, 0)),
+            ((4, 3), (0, 1)),
+            ((5, 3), (0, 2)),
+            ((6, 3), (0, 3)),
+        ] {
+            state.make_move(black).unwrap();
+            state.make_move(white).unwrap();
+        }
+
+        let (mov, win_rate) = mcts_best_move(&state);
+        assert!(matches!(mov, Some((2, 3)) | Some((7, 3))));
+        assert_eq!(win_rate, 1.0);
+    }
+}
@@ -0,0 +1,278 @@
+use crate::{
+    ai::{evaluate, generate_moves, node_counter, reset_node_counter, TimeKeeper, TIME_THRESHOLD},
+    game::{GameEnd, GameState, Point},
+};
+use rayon::prelude::*;
+use std::{
+    cmp::Reverse,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+};
+
+/// A root move's search outcome at one depth: its evaluation and principal
+/// variation, or `None` if the time budget ran out before it finished.
+type RootResult = (Point, Option<(i32, Vec<Point>)>);
+
+/// The result of analyzing a position: the principal variation (the line
+/// both sides are expected to play) and every root move's score, so a
+/// caller can show the engine's plan instead of just its next move.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub best_line: Vec<Point>,
+    pub scored_moves: Vec<(Point, i32)>,
+}
+
+/// An incremental update streamed while a long analysis is still running.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    pub depth: i32,
+    pub nodes_searched: u32,
+    pub best_move: Option<Point>,
+}
+
+/// Like `ai::minimax`, but also threads back the best child move at each
+/// node so the root call can reconstruct the full principal variation.
+fn minimax_with_line(
+    state: &GameState,
+    depth: i32,
+    alpha: i32,
+    beta: i32,
+    maximizing_player: bool,
+    time_keeper: &TimeKeeper,
+) -> Option<(i32, Vec<Point>)> {
+    if time_keeper.is_time_over() {
+        return None;
+    }
+
+    if depth == 0 {
+        return Some((evaluate(state), Vec::new()));
+    }
+
+    let mut alpha = alpha;
+    let mut beta = beta;
+    let mut best_line = Vec::new();
+
+    if maximizing_player {
+        let mut max_eval = i32::MIN;
+        for action in generate_moves(state) {
+            let mut new_state = state.clone();
+            let result = new_state.make_move(action).expect("move to possible moves");
+            let (eval, mut line) = match result {
+                GameEnd::NotEnd => {
+                    minimax_with_line(&new_state, depth - 1, alpha, beta, false, time_keeper)?
+                }
+                GameEnd::Win => (i32::MAX, Vec::new()),
+                GameEnd::Lost | GameEnd::Forbidden => (i32::MIN, Vec::new()),
+            };
+            if eval > max_eval {
+                max_eval = eval;
+                line.insert(0, action);
+                best_line = line;
+            }
+            alpha = alpha.max(eval);
+            if beta <= alpha {
+                break;
+            }
+        }
+        Some((max_eval, best_line))
+    } else {
+        let mut min_eval = i32::MAX;
+        for action in generate_moves(state) {
+            let mut new_state = state.clone();
+            let result = new_state.make_move(action).expect("move to possible moves");
+            let (eval, mut line) = match result {
+                GameEnd::NotEnd => {
+                    minimax_with_line(&new_state, depth - 1, alpha, beta, true, time_keeper)?
+                }
+                GameEnd::Win => (i32::MIN, Vec::new()),
+                GameEnd::Lost | GameEnd::Forbidden => (i32::MAX, Vec::new()),
+            };
+            if eval < min_eval {
+                min_eval = eval;
+                line.insert(0, action);
+                best_line = line;
+            }
+            beta = beta.min(eval);
+            if beta <= alpha {
+                break;
+            }
+        }
+        Some((min_eval, best_line))
+    }
+}
+
+/// Analyzes `state`, iteratively deepening until the time budget runs out,
+/// and returns the principal variation and per-root-move scores from the
+/// last fully completed depth.
+///
+/// This re-implements `ai::minimax`'s alpha-beta search rather than sharing
+/// its (private) Zobrist transposition table, so within the same time budget
+/// it typically reaches a shallower depth than `ai::best_move` and the
+/// reported line can diverge from the move the engine would actually play.
+pub fn analyze(state: &GameState) -> Analysis {
+    analyze_with_progress(state, None)
+}
+
+/// Same as `analyze`, but sends a `Progress` update after every completed
+/// depth so a caller running this on a background thread can display a live
+/// "thinking" view.
+pub fn analyze_with_progress(state: &GameState, progress: Option<Sender<Progress>>) -> Analysis {
+    reset_node_counter();
+
+    // `generate_moves` only considers cells adjacent to an existing stone,
+    // so on an empty board it returns nothing at any depth; without this
+    // guard the loop below would run through empty depths, sending a
+    // `Progress` update for each, until the time budget ran out.
+    if generate_moves(state).is_empty() {
+        return Analysis {
+            best_line: Vec::new(),
+            scored_moves: Vec::new(),
+        };
+    }
+
+    let time_keeper = TimeKeeper::new(TIME_THRESHOLD);
+
+    let mut best_line = Vec::new();
+    let mut scored_moves = Vec::new();
+    let mut depth = 1;
+
+    while !time_keeper.is_time_over() {
+        let results: Vec<RootResult> = generate_moves(state)
+            .into_par_iter()
+            .map(|action| {
+                let mut state_snapshot = state.clone();
+                let end = state_snapshot
+                    .make_move(action)
+                    .expect("move to possible moves");
+                let result = match end {
+                    GameEnd::NotEnd => minimax_with_line(
+                        &state_snapshot,
+                        depth,
+                        i32::MIN,
+                        i32::MAX,
+                        false,
+                        &time_keeper,
+                    ),
+                    GameEnd::Win => Some((i32::MAX, Vec::new())),
+                    GameEnd::Lost | GameEnd::Forbidden => Some((i32::MIN, Vec::new())),
+                };
+
+                (action, result)
+            })
+            .collect();
+
+        // Mirrors `ai::best_move`: a depth with any bailed-out branch can't
+        // be compared fairly, so it is thrown away wholesale.
+        if results.iter().any(|(_, result)| result.is_none()) {
+            break;
+        }
+
+        let mut depth_scored_moves = Vec::new();
+        let mut depth_best_eval = i32::MIN;
+        let mut depth_best_line = Vec::new();
+
+        for (action, result) in results {
+            let (eval, mut line) = result.expect("checked for None above");
+            depth_scored_moves.push((action, eval));
+            if eval > depth_best_eval {
+                depth_best_eval = eval;
+                line.insert(0, action);
+                depth_best_line = line;
+            }
+        }
+
+        depth_scored_moves.sort_by_key(|(_, eval)| Reverse(*eval));
+        scored_moves = depth_scored_moves;
+        best_line = depth_best_line;
+
+        if let Some(sender) = &progress {
+            let _ = sender.send(Progress {
+                depth,
+                nodes_searched: node_counter(),
+                best_move: best_line.first().copied(),
+            });
+        }
+
+        depth += 1;
+    }
+
+    Analysis {
+        best_line,
+        scored_moves,
+    }
+}
+
+/// Runs `analyze_with_progress` on a background thread, returning a receiver
+/// for its progress updates and a handle to join for the final `Analysis`;
+/// the caller blocks only while it reads the receiver or joins the handle.
+pub fn analyze_async(state: GameState) -> (Receiver<Progress>, JoinHandle<Analysis>) {
+    let (sender, receiver) = mpsc::channel();
+    let handle = thread::spawn(move || analyze_with_progress(&state, Some(sender)));
+
+    (receiver, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ai::SEARCH_TEST_LOCK, game::RuleSet};
+
+    #[test]
+    fn analyze_never_ranks_a_forbidden_root_move_as_best() {
+        // `analyze` reuses `ai::evaluate`'s process-global cache and node
+        // counter, so it takes the same lock `ai`'s own search tests do to
+        // avoid two concurrently-running searches corrupting each other's
+        // counts (see `ai::SEARCH_TEST_LOCK`).
+        let _guard = SEARCH_TEST_LOCK.lock().expect("should obtain lock");
+
+        // Same forbidden-overline setup as `ai::tests::best_move_never_picks_a_forbidden_root_move`:
+        // (4, 0) looks like the strongest move on the board but is rejected
+        // by Renju, so it must not come back as the top-scored move.
+        let mut state = GameState::with_rule_set(RuleSet::Renju);
+        let black_moves = [(0, 0), (1, 0), (2, 0), (3, 0), (5, 0)];
+        let white_moves = [(10, 10), (10, 11), (10, 12), (10, 13), (10, 14)];
+        for (black, white) in black_moves.into_iter().zip(white_moves) {
+            state.make_move(black).unwrap();
+            state.make_move(white).unwrap();
+        }
+
+        let mut probe = state.clone();
+        assert_eq!(probe.make_move((4, 0)).unwrap(), GameEnd::Forbidden);
+
+        let analysis = analyze(&state);
+        assert_ne!(analysis.best_line.first(), Some(&(4, 0)));
+        let forbidden_score = analysis
+            .scored_moves
+            .iter()
+            .find(|(point, _)| *point == (4, 0))
+            .map(|(_, score)| *score);
+        assert_eq!(forbidden_score, Some(i32::MIN));
+    }
+
+    #[test]
+    fn minimax_with_line_reconstructs_the_principal_variation_for_an_immediate_win() {
+        let _guard = SEARCH_TEST_LOCK.lock().expect("should obtain lock");
+
+        // Black has an open four on row 3: at depth 1 either open end wins
+        // outright and the search cuts off at the first win it finds, so the
+        // reconstructed line is that single move at one of the two ends.
+        let mut state = GameState::new();
+        for (black, white) in [
+            ((3, 3), (0, 0)),
+            ((4, 3), (0, 1)),
+            ((5, 3), (0, 2)),
+            ((6, 3), (0, 3)),
+        ] {
+            state.make_move(black).unwrap();
+            state.make_move(white).unwrap();
+        }
+
+        let time_keeper = TimeKeeper::new(TIME_THRESHOLD);
+        let (eval, line) = minimax_with_line(&state, 1, i32::MIN, i32::MAX, true, &time_keeper)
+            .expect("search should complete within the time budget");
+
+        assert_eq!(eval, i32::MAX);
+        assert_eq!(line.len(), 1);
+        assert!(matches!(line[0], (2, 3) | (7, 3)));
+    }
+}
@@ -1,6 +1,12 @@
-use std::fmt::{Display, Error};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Error},
+    hash::{Hash, Hasher},
+};
 
 use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use rand::Rng;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Piece {
@@ -13,42 +19,174 @@ pub type BoardState = [[Piece; 15]; 15];
 
 pub type Point = (usize, usize);
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+/// Per cell/color random keys used for incremental Zobrist hashing, plus one
+/// extra key (see `ZOBRIST_SIDE_KEY`) folded in whenever the side to move
+/// changes.
+type ZobristTable = [[[u64; 2]; 15]; 15];
+
+lazy_static! {
+    static ref ZOBRIST_KEYS: ZobristTable = {
+        let mut rng = rand::thread_rng();
+        let mut table = [[[0u64; 2]; 15]; 15];
+        for row in table.iter_mut() {
+            for cell in row.iter_mut() {
+                for key in cell.iter_mut() {
+                    *key = rng.gen();
+                }
+            }
+        }
+        table
+    };
+    static ref ZOBRIST_SIDE_KEY: u64 = rand::thread_rng().gen();
+}
+
+fn zobrist_index(piece: Piece) -> Option<usize> {
+    match piece {
+        Piece::Empty => None,
+        Piece::Black => Some(0),
+        Piece::White => Some(1),
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct GameState {
     board: BoardState,
     current_turn: Piece,
+    /// Running Zobrist hash of `board` and `current_turn`, updated
+    /// incrementally in `make_move` instead of being recomputed from the
+    /// whole board on every lookup.
+    hash: u64,
+    /// Moves played so far, in order, for `undo` and game records.
+    history: Vec<Point>,
+    rule_set: RuleSet,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// `PartialEq`/`Eq`/`Hash` are restricted to the fields `evaluate` actually
+/// reads (`board`, `current_turn`) so two states reached via different move
+/// orders, or differing only in `history`/`rule_set`, still share
+/// `EVALUATE_CACHE` entries. `hash` is always kept consistent with
+/// `board`/`current_turn` by `make_move`/`undo`, so hashing it directly is
+/// equivalent to hashing the board and cheaper.
+impl PartialEq for GameState {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board && self.current_turn == other.current_turn
+    }
+}
+
+impl Eq for GameState {}
+
+impl Hash for GameState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameEnd {
     NotEnd,
     Win,
     Lost,
+    /// The move was legal as a placement but is forbidden under the active
+    /// `RuleSet` (Renju's overline/double-four/double-three restrictions on
+    /// Black); the board is left unchanged.
+    Forbidden,
+}
+
+/// Which set of Gomoku win/forbidden-move rules `GameState` enforces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleSet {
+    /// Any link of five or more in a row wins.
+    Freestyle,
+    /// A link of exactly five wins; six or more (an "overline") does not.
+    Standard,
+    /// `Standard` win rules, plus Black may not play an overline, a
+    /// double-four, or a double-three.
+    Renju,
+}
+
+impl Display for RuleSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RuleSet::Freestyle => "freestyle",
+            RuleSet::Standard => "standard",
+            RuleSet::Renju => "renju",
+        })
+    }
+}
+
+impl std::str::FromStr for RuleSet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "freestyle" => Ok(RuleSet::Freestyle),
+            "standard" => Ok(RuleSet::Standard),
+            "renju" => Ok(RuleSet::Renju),
+            _ => Err(anyhow!("Unknown rule set: {s}")),
+        }
+    }
 }
 
 impl GameState {
     pub fn new() -> Self {
+        GameState::with_rule_set(RuleSet::Freestyle)
+    }
+
+    /// Creates a new game enforcing `rule_set`'s win and forbidden-move
+    /// conditions instead of the default `Freestyle` rules.
+    pub fn with_rule_set(rule_set: RuleSet) -> Self {
         GameState {
             board: [[Piece::Empty; 15]; 15],
             current_turn: Piece::Black, // 通常，黑子先手
+            hash: 0,
+            history: Vec::new(),
+            rule_set,
         }
     }
 
+    pub fn rule_set(&self) -> RuleSet {
+        self.rule_set
+    }
+
     pub fn current_turn(&self) -> Piece {
         self.current_turn
     }
 
+    /// Incremental Zobrist hash of the current board and side to move, for
+    /// use as a cheap transposition table key.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
     pub fn piece(&self, point: Point) -> Piece {
         self.board[point.0][point.1]
     }
 
+    /// Moves played so far, in the order they were made.
+    pub fn history(&self) -> &[Point] {
+        &self.history
+    }
+
     pub fn make_move(&mut self, point: Point) -> Result<GameEnd> {
         let (x, y) = point;
         if x >= 15 || y >= 15 || self.board[x][y] != Piece::Empty {
             return Err(anyhow!("Invalid move"));
         }
 
+        if self.rule_set == RuleSet::Renju
+            && self.current_turn == Piece::Black
+            && self.is_forbidden_move(point)
+        {
+            return Ok(GameEnd::Forbidden);
+        }
+
         self.board[x][y] = self.current_turn;
+        if let Some(index) = zobrist_index(self.current_turn) {
+            self.hash ^= ZOBRIST_KEYS[x][y][index];
+        }
+        self.hash ^= *ZOBRIST_SIDE_KEY;
+        self.history.push(point);
+
         self.current_turn = match self.current_turn {
             Piece::Black => Piece::White,
             Piece::White => Piece::Black,
@@ -56,13 +194,106 @@ impl GameState {
         };
 
         let link_count = self.get_max_link_count(point);
-        if link_count >= 5 {
+        let wins = match self.rule_set {
+            RuleSet::Freestyle => link_count >= 5,
+            RuleSet::Standard | RuleSet::Renju => link_count == 5,
+        };
+        if wins {
             return Ok(GameEnd::Win);
         }
 
         Ok(GameEnd::NotEnd)
     }
 
+    /// Takes back the last move, restoring the board and side to move to
+    /// what they were before it was made.
+    pub fn undo(&mut self) -> Result<()> {
+        let point = self
+            .history
+            .pop()
+            .ok_or_else(|| anyhow!("No move to undo"))?;
+        let (x, y) = point;
+
+        let piece = self.board[x][y];
+        self.board[x][y] = Piece::Empty;
+        if let Some(index) = zobrist_index(piece) {
+            self.hash ^= ZOBRIST_KEYS[x][y][index];
+        }
+        self.hash ^= *ZOBRIST_SIDE_KEY;
+
+        self.current_turn = match self.current_turn {
+            Piece::Black => Piece::White,
+            Piece::White => Piece::Black,
+            _ => unreachable!(),
+        };
+
+        Ok(())
+    }
+
+    /// Serializes the rule set and moves played so far: a `rule_set=...`
+    /// header line followed by one `x,y` pair per move, so `from_record` can
+    /// replay the game under the rules it was actually played with.
+    pub fn to_record(&self) -> String {
+        let mut lines = vec![format!("rule_set={}", self.rule_set)];
+        lines.extend(self.history.iter().map(|(x, y)| format!("{x},{y}")));
+        lines.join("\n")
+    }
+
+    /// Replays a record produced by `to_record`, rejecting it if the header
+    /// or any move line is malformed, or any move in it is illegal (or
+    /// forbidden) under the record's own rule set.
+    pub fn from_record(record: &str) -> Result<GameState> {
+        let mut lines = record.lines();
+
+        let header = lines
+            .next()
+            .ok_or_else(|| anyhow!("Empty record"))?
+            .trim();
+        let rule_set = header
+            .strip_prefix("rule_set=")
+            .ok_or_else(|| anyhow!("Missing rule_set header: {header}"))?
+            .parse()
+            .map_err(|_| anyhow!("Unknown rule set in header: {header}"))?;
+
+        let mut state = GameState::with_rule_set(rule_set);
+
+        for (line_no, line) in lines.enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (x, y) = line
+                .split_once(',')
+                .ok_or_else(|| anyhow!("Invalid move on line {}: {line}", line_no + 2))?;
+            let x: usize = x
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("Invalid move on line {}: {line}", line_no + 2))?;
+            let y: usize = y
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("Invalid move on line {}: {line}", line_no + 2))?;
+
+            match state.make_move((x, y)) {
+                Ok(GameEnd::Forbidden) => {
+                    return Err(anyhow!("Forbidden move on line {}: {line}", line_no + 2))
+                }
+                Ok(_) => {}
+                Err(err) => return Err(anyhow!("Illegal move on line {}: {err}", line_no + 2)),
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Length of the longest line of `point`'s own piece running through it,
+    /// counting `point` itself, in whichever of the four directions
+    /// (horizontal/vertical/either diagonal) is longest.
+    ///
+    /// `make_move` compares this directly against the active rule set's
+    /// winning length, so it must return a positive count: a flipped sign
+    /// here makes every `wins` check false and no one can ever win.
     fn get_max_link_count(&self, point: Point) -> i32 {
         let piece = self.piece(point);
         let mut link_count = 0;
@@ -91,8 +322,210 @@ impl GameState {
             link_count = link_count.max(count);
         }
 
-        -link_count
+        link_count
     }
+
+    /// Whether placing Black at `point` would be an overline, a double-four,
+    /// or a double-three, which Renju forbids Black (but not White) from
+    /// playing.
+    fn is_forbidden_move(&self, point: Point) -> bool {
+        let mut scratch = self.clone();
+        scratch.board[point.0][point.1] = Piece::Black;
+
+        if scratch.get_max_link_count(point) > 5 {
+            return true;
+        }
+
+        let (fours, threes) = scratch.count_open_threats(point, Piece::Black);
+        fours >= 2 || threes >= 2
+    }
+
+    /// Counts, across all four directions through `point`, how many open
+    /// fours (`RushFour` or `LiveFour`) and open threes (`LiveThree`) `mover`
+    /// has, for Renju's double-four/double-three forbidden-move checks.
+    fn count_open_threats(&self, point: Point, mover: Piece) -> (i32, i32) {
+        let patterns = line_patterns();
+        let mut fours = 0;
+        let mut threes = 0;
+
+        for dir in [(1, 0), (0, 1), (1, 1), (1, -1)] {
+            let cells = self.line_through(point, dir);
+            let sequence = line_sequence(self, &cells, point, mover);
+            for (piece_state, count) in count_subarrays(&sequence, &patterns) {
+                match piece_state {
+                    PieceState::RushFour | PieceState::LiveFour => fours += count,
+                    PieceState::LiveThree => threes += count,
+                    _ => {}
+                }
+            }
+        }
+
+        (fours, threes)
+    }
+
+    /// Every point on the board along the line through `point` in direction
+    /// `dir` (and its opposite), in order.
+    fn line_through(&self, point: Point, dir: (isize, isize)) -> Vec<Point> {
+        let mut start = (point.0 as isize, point.1 as isize);
+        loop {
+            let prev = (start.0 - dir.0, start.1 - dir.1);
+            if prev.0 < 0 || prev.1 < 0 || prev.0 >= 15 || prev.1 >= 15 {
+                break;
+            }
+            start = prev;
+        }
+
+        let mut cells = Vec::new();
+        let mut cursor = start;
+        loop {
+            cells.push((cursor.0 as usize, cursor.1 as usize));
+            let next = (cursor.0 + dir.0, cursor.1 + dir.1);
+            if next.0 < 0 || next.1 < 0 || next.0 >= 15 || next.1 >= 15 {
+                break;
+            }
+            cursor = next;
+        }
+
+        cells
+    }
+}
+
+/// Encodes `cells` (a line of board points through `point`) as a 0/1/2
+/// sequence for pattern matching: `point` itself counts as `mover`'s piece
+/// regardless of what (if anything) is actually on the board there, other
+/// cells matching `mover` are `1`, empty cells are `0`, and anything else
+/// (including out-of-line sentinels) is `2`.
+pub(crate) fn line_sequence(
+    state: &GameState,
+    cells: &[Point],
+    point: Point,
+    mover: Piece,
+) -> Vec<i32> {
+    let mut sequence = Vec::with_capacity(cells.len() + 2);
+    sequence.push(2);
+
+    for &cell in cells {
+        let piece = if cell == point { mover } else { state.piece(cell) };
+        sequence.push(if piece == Piece::Empty {
+            0
+        } else if piece == mover {
+            1
+        } else {
+            2
+        });
+    }
+
+    sequence.push(2);
+    sequence
+}
+
+/// The line patterns recognized by `sequence` (leaf evaluation) and
+/// `count_open_threats` (Renju forbidden-move detection), e.g. a run of
+/// five (`Five`) or an open three (`LiveThree`).
+pub(crate) fn line_patterns() -> Vec<(Vec<i32>, PieceState)> {
+    with_inv(vec![
+        (vec![1, 1, 1, 1, 1, 1], PieceState::Long),
+        (vec![1, 1, 1, 1, 1], PieceState::Five),
+        (vec![0, 1, 1, 1, 1, 0], PieceState::LiveFour),
+        (vec![0, 1, 1, 1, 0, 0], PieceState::LiveThree),
+        (vec![0, 1, 0, 1, 1, 0], PieceState::LiveThree),
+        (vec![0, 0, 1, 1, 0, 0], PieceState::LiveTwo),
+        (vec![2, 1, 1, 1, 1, 0], PieceState::RushFour),
+        (vec![1, 1, 0, 1, 1], PieceState::RushFour),
+        (vec![1, 0, 1, 1, 1], PieceState::RushFour),
+        (vec![1, 0, 0, 1, 1], PieceState::SleepThree),
+        (vec![2, 1, 1, 1, 0, 0], PieceState::SleepThree),
+        (vec![2, 1, 1, 0, 1, 0], PieceState::SleepThree),
+        (vec![2, 1, 0, 1, 1, 0], PieceState::SleepThree),
+        (vec![2, 1, 1, 0, 0, 0], PieceState::SleepTwo),
+        (vec![2, 1, 0, 1, 0, 0], PieceState::SleepTwo),
+        (vec![2, 1, 0, 0, 1, 0], PieceState::SleepTwo),
+        (vec![2, 1, 0, 0, 0, 1], PieceState::SleepTwo),
+        (vec![2, 0, 1, 1, 0, 0, 2], PieceState::SleepTwo),
+        (vec![2, 0, 1, 0, 1, 0, 2], PieceState::SleepTwo),
+    ])
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub(crate) enum PieceState {
+    Long,
+    Five,
+    RushFour,
+    LiveFour,
+    LiveThree,
+    SleepThree,
+    LiveTwo,
+    SleepTwo,
+}
+
+pub(crate) fn count_subarrays(
+    array: &[i32],
+    subarrays: &[(Vec<i32>, PieceState)],
+) -> HashMap<PieceState, i32> {
+    let array_len = array.len();
+    let mut count = HashMap::new();
+    let mut len = usize::MAX;
+
+    for (subarray, _) in subarrays {
+        if subarray.len() < len {
+            len = subarray.len();
+        }
+    }
+
+    if array_len < len {
+        return count;
+    }
+
+    let mut i = 0;
+    while i < array_len - len + 1 {
+        let mut found = false;
+        for (subarray, state) in subarrays {
+            let subarray_len = subarray.len();
+            if i + subarray_len <= array_len && &array[i..i + subarray_len] == subarray.as_slice() {
+                let c = count.get(state).unwrap_or(&0) + 1;
+                count.insert(*state, c);
+                i += subarray_len;
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            i += 1;
+        }
+    }
+
+    count
+}
+
+fn loopback(sequence: &[i32]) -> bool {
+    let mut left = 0;
+    let mut right = sequence.len() - 1;
+    while left < right {
+        if sequence[left] != sequence[right] {
+            return false;
+        }
+        left += 1;
+        right -= 1;
+    }
+    true
+}
+
+fn with_inv(patterns: Vec<(Vec<i32>, PieceState)>) -> Vec<(Vec<i32>, PieceState)> {
+    let mut result = Vec::new();
+
+    for (pattern, state) in patterns {
+        result.push((pattern.clone(), state));
+        if loopback(&pattern) {
+            continue;
+        }
+        result.push((inv(&pattern), state))
+    }
+
+    result
+}
+
+fn inv(sequence: &[i32]) -> Vec<i32> {
+    sequence.iter().rev().copied().collect()
 }
 
 impl Display for GameState {
@@ -146,3 +579,90 @@ impl Display for GameState {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transposed_states_are_equal_and_hash_equal() {
+        // Same final board reached by two different move orders: Black's
+        // two moves are swapped, but each player's moves still land on
+        // turns of the same parity, so the resulting board and side to
+        // move match even though `history` doesn't.
+        let mut via_a = GameState::new();
+        via_a.make_move((7, 7)).unwrap(); // Black
+        via_a.make_move((1, 1)).unwrap(); // White
+        via_a.make_move((8, 8)).unwrap(); // Black
+        via_a.make_move((2, 2)).unwrap(); // White
+
+        let mut via_b = GameState::new();
+        via_b.make_move((8, 8)).unwrap(); // Black
+        via_b.make_move((1, 1)).unwrap(); // White
+        via_b.make_move((7, 7)).unwrap(); // Black
+        via_b.make_move((2, 2)).unwrap(); // White
+
+        assert_ne!(via_a.history(), via_b.history());
+        assert_eq!(via_a, via_b);
+        assert_eq!(via_a.hash(), via_b.hash());
+
+        let mut hasher_a = std::collections::hash_map::DefaultHasher::new();
+        Hash::hash(&via_a, &mut hasher_a);
+        let mut hasher_b = std::collections::hash_map::DefaultHasher::new();
+        Hash::hash(&via_b, &mut hasher_b);
+        assert_eq!(hasher_a.finish(), hasher_b.finish());
+    }
+
+    #[test]
+    fn undo_restores_board_and_turn() {
+        let mut state = GameState::new();
+        state.make_move((7, 7)).unwrap();
+        let before_second_move = state.clone();
+        state.make_move((8, 8)).unwrap();
+
+        state.undo().unwrap();
+
+        assert_eq!(state, before_second_move);
+        assert_eq!(state.hash(), before_second_move.hash());
+        assert_eq!(state.history(), before_second_move.history());
+    }
+
+    #[test]
+    fn record_round_trip_preserves_rule_set() {
+        let mut state = GameState::with_rule_set(RuleSet::Renju);
+        state.make_move((7, 7)).unwrap();
+        state.make_move((8, 8)).unwrap();
+
+        let record = state.to_record();
+        let loaded = GameState::from_record(&record).unwrap();
+
+        assert_eq!(loaded.rule_set(), RuleSet::Renju);
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.history(), state.history());
+    }
+
+    #[test]
+    fn freestyle_allows_overline_but_standard_does_not() {
+        // Six in a row through (0..=5, 0): an overline.
+        let mut freestyle = GameState::with_rule_set(RuleSet::Freestyle);
+        let mut standard = GameState::with_rule_set(RuleSet::Standard);
+        for x in 0..6 {
+            let end_freestyle = freestyle.make_move((x, 0)).unwrap();
+            let end_standard = standard.make_move((x, 0)).unwrap();
+            if x < 5 {
+                if x == 4 {
+                    // Both rule sets win on the fifth stone; get_max_link_count
+                    // must return a positive link length for this to fire at
+                    // all (a flipped sign makes `wins` false forever).
+                    assert_eq!(end_freestyle, GameEnd::Win);
+                    assert_eq!(end_standard, GameEnd::Win);
+                }
+                freestyle.make_move((x, 1)).unwrap();
+                standard.make_move((x, 1)).unwrap();
+            } else {
+                assert_eq!(end_freestyle, GameEnd::Win);
+                assert_eq!(end_standard, GameEnd::NotEnd);
+            }
+        }
+    }
+}
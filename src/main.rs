@@ -1,14 +1,64 @@
-use dialoguer::Input;
+use anyhow::{Context, Result};
+use dialoguer::{Input, Select};
 use game::GameState;
-use std::time::SystemTime;
+use std::{fs, time::SystemTime};
 
-use crate::game::GameEnd;
+use crate::game::{GameEnd, RuleSet};
 
 pub mod ai;
+pub mod analysis;
 pub mod game;
 
+/// Runs `analysis::analyze_async` on `state`, printing each depth's progress
+/// as it streams in, then the finished principal variation and top scored
+/// moves, so the `analyze` command can show the engine's plan. The search
+/// runs on a background thread, but this function waits for it to finish,
+/// so the game loop does not prompt again until the analysis is done.
+fn run_analysis(state: &GameState) {
+    let (progress, handle) = analysis::analyze_async(state.clone());
+
+    for update in progress {
+        println!(
+            "  depth {}: {} nodes, best so far {:?}",
+            update.depth, update.nodes_searched, update.best_move
+        );
+    }
+
+    let analysis = handle.join().expect("analysis thread should not panic");
+    println!("Principal variation: {:?}", analysis.best_line);
+    for (point, score) in analysis.scored_moves.iter().take(5) {
+        println!("  {point:?}: {score}");
+    }
+}
+
+fn save_game(path: &str, game_state: &GameState) -> Result<()> {
+    fs::write(path, game_state.to_record()).with_context(|| format!("writing {path}"))
+}
+
+fn load_game(path: &str) -> Result<GameState> {
+    let record = fs::read_to_string(path).with_context(|| format!("reading {path}"))?;
+    GameState::from_record(&record)
+}
+
+/// Asks the player which win/forbidden-move rules to play under, so the AI's
+/// own move legality and evaluation follow the same rule set for the rest of
+/// the game.
+fn choose_rule_set() -> RuleSet {
+    let rule_sets = [RuleSet::Freestyle, RuleSet::Standard, RuleSet::Renju];
+    let labels = ["Freestyle", "Standard", "Renju"];
+
+    let selection = Select::new()
+        .with_prompt("rule set")
+        .items(&labels)
+        .default(0)
+        .interact()
+        .expect("select rule set");
+
+    rule_sets[selection]
+}
+
 fn main() {
-    let mut game_state = GameState::new();
+    let mut game_state = GameState::with_rule_set(choose_rule_set());
     let mut turn = 0;
 
     loop {
@@ -17,22 +67,67 @@ fn main() {
         println!("{game_state}");
 
         if turn % 2 == 0 {
-            let x = Input::new()
-                .with_prompt("x")
-                .interact_text()
-                .expect("input x");
-            let y = Input::new()
-                .with_prompt("y")
+            let input: String = Input::new()
+                .with_prompt("move (x,y | undo | save <path> | load <path> | analyze)")
                 .interact_text()
-                .expect("input y");
+                .expect("input move");
+            let input = input.trim();
 
-            match game_state.make_move((x, y)) {
+            if input == "analyze" {
+                run_analysis(&game_state);
+                continue;
+            }
+
+            if let Some(path) = input.strip_prefix("save ") {
+                match save_game(path.trim(), &game_state) {
+                    Ok(()) => println!("Saved to {}", path.trim()),
+                    Err(err) => println!("Save failed: {err}"),
+                }
+                continue;
+            }
+
+            if let Some(path) = input.strip_prefix("load ") {
+                match load_game(path.trim()) {
+                    Ok(loaded) => {
+                        turn = loaded.history().len() as i32;
+                        game_state = loaded;
+                        println!("Loaded from {}", path.trim());
+                    }
+                    Err(err) => println!("Load failed: {err}"),
+                }
+                continue;
+            }
+
+            if input == "undo" {
+                match game_state.undo() {
+                    Ok(()) => {
+                        turn = game_state.history().len() as i32;
+                        println!("Move undone");
+                    }
+                    Err(err) => println!("Undo failed: {err}"),
+                }
+                continue;
+            }
+
+            let point = input
+                .split_once(',')
+                .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)));
+            let Some(point) = point else {
+                println!("Invalid input: {input}");
+                continue;
+            };
+
+            match game_state.make_move(point) {
                 Ok(GameEnd::Win) => {
                     println!("You WIN")
                 }
                 Ok(GameEnd::Lost) => {
                     println!("You Lost")
                 }
+                Ok(GameEnd::Forbidden) => {
+                    println!("Forbidden move: {point:?}");
+                    continue;
+                }
                 Ok(GameEnd::NotEnd) => {}
                 Err(err) => {
                     println!("Move fail: {err}");
@@ -60,6 +155,9 @@ fn main() {
                 GameEnd::Lost => {
                     println!("AI Lost")
                 }
+                GameEnd::Forbidden => {
+                    println!("AI attempted a forbidden move: {point:?}")
+                }
                 GameEnd::NotEnd => {}
             }
         }